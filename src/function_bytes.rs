@@ -0,0 +1,128 @@
+//! Serde helpers for encoding a `GOFunction` as a compact byte string instead of a
+//! per-byte sequence.
+//!
+//! A bare `Vec<u8>` serializes as a generic sequence in most `serde::Serializer`s
+//! (JSON included), which emits one token per byte and is both slower and far
+//! larger on the wire than the function actually needs to be. These types route
+//! through `serialize_bytes`/`deserialize_bytes` instead, so formats that
+//! special-case byte strings (bincode, MessagePack, CBOR, ...) store the function
+//! as a single blob. Human-readable formats (JSON, ...) get a base64 string
+//! instead, so the function stays compact and diff-friendly rather than
+//! expanding into escaped raw bytes.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use base64::Engine;
+use ph::fmph::GOFunction;
+use serde::de::{Error, Visitor};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Borrowed view used when serializing a `GOFunction` field.
+pub(crate) struct FunctionBytesRef<'a>(pub(crate) &'a GOFunction);
+
+impl<'a> Serialize for FunctionBytesRef<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut bytes = Vec::with_capacity(self.0.write_bytes());
+        self.0
+            .write(&mut bytes)
+            .map_err(|_| S::Error::custom("couldn't write hash function"))?;
+
+        if serializer.is_human_readable() {
+            let encoded: String = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            serializer.serialize_str(&encoded)
+        } else {
+            serializer.serialize_bytes(&bytes)
+        }
+    }
+}
+
+/// Owned wrapper produced when deserializing a `GOFunction` field.
+pub(crate) struct FunctionBytes(pub(crate) GOFunction);
+
+fn read_function<'de, E: Error>(bytes: Cow<'de, [u8]>) -> Result<FunctionBytes, E> {
+    GOFunction::read(&mut bytes.as_ref())
+        .map(FunctionBytes)
+        .map_err(|_| E::custom("invalid bytes: expected bytes representing a ph::GOFunction"))
+}
+
+struct FunctionBytesVisitor;
+
+impl<'de> Visitor<'de> for FunctionBytesVisitor {
+    type Value = FunctionBytes;
+
+    fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_str("a byte string representing a ph::GOFunction")
+    }
+
+    fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        read_function(Cow::Borrowed(v))
+    }
+
+    // Called instead of `visit_bytes` when the deserializer can hand us a slice
+    // that lives for the whole deserialization ('de), e.g. a bincode reader over
+    // an in-memory buffer or a borrowed mmap. This skips the deserializer's own
+    // copy into an owned `Vec<u8>` before we hand the bytes to `GOFunction::read`.
+    fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        read_function(Cow::Borrowed(v))
+    }
+
+    fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        read_function(Cow::Owned(v))
+    }
+
+    // Taken when the function was serialized as base64 for a human-readable
+    // format (see `FunctionBytesRef::serialize`).
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(v)
+            .map_err(|_| E::custom("invalid base64: expected a ph::GOFunction"))?;
+        read_function(Cow::Owned(bytes))
+    }
+
+    // Covers binary formats that hint `deserialize_bytes` but still hand back
+    // a generic sequence instead of calling `visit_bytes`. This is NOT reachable
+    // for human-readable formats (they call `deserialize_str` and land in
+    // `visit_str` above), so it does not make the pre-base64 per-byte JSON array
+    // readable again - that JSON representation changed incompatibly.
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: serde::de::SeqAccess<'de>,
+    {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+impl<'de> Deserialize<'de> for FunctionBytes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(FunctionBytesVisitor)
+        } else {
+            deserializer.deserialize_bytes(FunctionBytesVisitor)
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use std::{borrow::Borrow, collections::HashMap, hash::Hash, marker::PhantomData, ops::Index};
+use alloc::vec::Vec;
+use core::{borrow::Borrow, hash::Hash, marker::PhantomData, ops::Index};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 use ph::fmph::{GOBuildConf, GOConf, GOFunction};
 
@@ -8,6 +11,7 @@ pub struct KeylessPerfectMap<K, V> {
     pub keys: PhantomData<K>,
 }
 
+#[cfg(feature = "std")]
 impl<KEY: Hash + Sync, VALUE: Hash + Sync> KeylessPerfectMap<KEY, VALUE> {
     pub fn from_map_invert<U: Into<VALUE>>(map: HashMap<U, KEY>) -> KeylessPerfectMap<KEY, VALUE> {
         let (values, keys): (Vec<_>, Vec<_>) = map.into_iter().unzip();
@@ -16,13 +20,16 @@ impl<KEY: Hash + Sync, VALUE: Hash + Sync> KeylessPerfectMap<KEY, VALUE> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<K: Hash + Sync, V> KeylessPerfectMap<K, V> {
     pub fn from_map<U: Into<V>>(map: HashMap<K, U>) -> KeylessPerfectMap<K, V> {
         let (keys, values): (Vec<_>, Vec<_>) = map.into_iter().unzip();
 
         KeylessPerfectMap::new(keys, values)
     }
+}
 
+impl<K: Hash + Sync, V> KeylessPerfectMap<K, V> {
     pub fn new<U: Into<V>>(keys: Vec<K>, values: Vec<U>) -> KeylessPerfectMap<K, V> {
         assert!(keys.len() == values.len());
 
@@ -34,7 +41,7 @@ impl<K: Hash + Sync, V> KeylessPerfectMap<K, V> {
         let map_len = values.len();
         let mut reordered_vals = Vec::with_capacity(map_len);
 
-        for (k, v) in keys.into_iter().zip(values.into_iter()) {
+        for (k, v) in keys.into_iter().zip(values) {
             let new_idx = hasher.get(&k).unwrap() as usize;
             reordered_vals.spare_capacity_mut()[new_idx].write(v.into());
         }
@@ -63,14 +70,14 @@ impl<K: Hash + Sync, V> KeylessPerfectMap<K, V> {
 
     pub fn get<Q>(&self, key: &Q) -> Option<&V>
     where
-        K: Borrow<Q> + std::cmp::PartialEq,
-        Q: Hash + std::cmp::PartialEq + ?Sized,
+        K: Borrow<Q> + core::cmp::PartialEq,
+        Q: Hash + core::cmp::PartialEq + ?Sized,
     {
 
         match self.function.get(key) {
             Some(idx) => {
                 let idx = idx as usize;
-                self.values.get(idx as usize)
+                self.values.get(idx)
             },
             None => None,
         }
@@ -83,11 +90,15 @@ impl<K: Hash + Sync, V> KeylessPerfectMap<K, V> {
     pub fn len(&self) -> usize {
         self.values.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
 }
 
 impl<K, Q: ?Sized, V> Index<&Q> for KeylessPerfectMap<K, V>
 where
-    K: Hash + Borrow<Q> + Sync + std::cmp::PartialEq,
+    K: Hash + Borrow<Q> + Sync + core::cmp::PartialEq,
     Q: Hash + PartialEq,
 {
     type Output = V;
@@ -109,17 +120,13 @@ impl<K: serde::Serialize, V: serde::Serialize> serde::Serialize for KeylessPerfe
     where
         S: serde::Serializer,
     {
-        use serde::ser::{Error, SerializeStruct};
+        use serde::ser::SerializeStruct;
+        use crate::function_bytes::FunctionBytesRef;
 
         let mut state = serializer.serialize_struct("PerfectMap", 3)?;
         state.serialize_field("values", &self.values)?;
         state.serialize_field("keys", &self.keys)?;
-
-        let mut hasher_bytes = Vec::with_capacity(self.function.write_bytes());
-        self.function
-            .write(&mut hasher_bytes)
-            .map_err(|_| S::Error::custom("couldn't write hash function"))?;
-        state.serialize_field("function", &hasher_bytes)?;
+        state.serialize_field("function", &FunctionBytesRef(&self.function))?;
         state.end()
     }
 }
@@ -132,6 +139,8 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
     where
         D: serde::Deserializer<'de>,
     {
+        use crate::function_bytes::FunctionBytes;
+
         #[derive(serde::Deserialize)]
         #[serde(field_identifier, rename_all = "lowercase")]
         enum Field {
@@ -149,7 +158,7 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
         {
             type Value = KeylessPerfectMap<K, V>;
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                 formatter.write_str("struct PerfectMap")
             }
 
@@ -160,18 +169,12 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                 let values: Vec<V> = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
-                let function_bytes: Vec<u8> = seq
+                let function: FunctionBytes = seq
                     .next_element()?
                     .ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
 
-                let function = GOFunction::read(&mut function_bytes.as_slice()).map_err(|_| {
-                    serde::de::Error::custom(
-                        "invalid bytes: expected bytes representing a ph::GOFunction",
-                    )
-                })?;
-
                 Ok(KeylessPerfectMap {
-                    function,
+                    function: function.0,
                     values,
                     keys: PhantomData
                 })
@@ -182,16 +185,16 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                 A: serde::de::MapAccess<'de>,
             {
                 let mut values: Option<Vec<V>> = None;
-                let mut function_bytes: Option<Vec<u8>> = None;
+                let mut function: Option<FunctionBytes> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Function => {
-                            if function_bytes.is_some() {
+                            if function.is_some() {
                                 return Err(serde::de::Error::duplicate_field("function"));
                             };
 
-                            function_bytes = Some(map.next_value()?);
+                            function = Some(map.next_value()?);
                         }
                         Field::Values => {
                             if values.is_some() {
@@ -202,24 +205,19 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                     }
                 }
 
-                let function_bytes: Vec<u8> =
-                    function_bytes.ok_or_else(|| serde::de::Error::missing_field("function"))?;
+                let function: FunctionBytes =
+                    function.ok_or_else(|| serde::de::Error::missing_field("function"))?;
                 let values = values.ok_or_else(|| serde::de::Error::missing_field("values"))?;
-                let function = GOFunction::read(&mut function_bytes.as_slice()).map_err(|_| {
-                    serde::de::Error::custom(
-                        "invalid bytes: expected bytes representing a ph::GOFunction",
-                    )
-                })?;
 
                 Ok(KeylessPerfectMap {
-                    function,
+                    function: function.0,
                     values,
                     keys: PhantomData
                 })
             }
         }
 
-        const FIELDS: &'static [&'static str] = &["values", "function"];
+        const FIELDS: &[&str] = &["values", "function"];
         deserializer.deserialize_struct(
             "PerfectMap",
             FIELDS,
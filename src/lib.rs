@@ -1,7 +1,22 @@
-use std::{collections::HashMap, hash::Hash, marker::PhantomData, borrow::Borrow, ops::Index};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::{hash::Hash, borrow::Borrow, ops::Index};
+#[cfg(feature = "serde")]
+use core::marker::PhantomData;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
 
 use ph::fmph::{GOBuildConf, GOConf, GOFunction};
 
+mod keyless;
+#[cfg(feature = "serde")]
+mod function_bytes;
+
+pub use keyless::KeylessPerfectMap;
+
 // impl<K: Hash + Sync, V> PerfectMapWithKeys<K, V> {
 //     pub fn from_map<U: Into<V>>(map: HashMap<K, U>) -> PerfectMap<K, V> {
 //         let (keys, values): (Vec<_>, Vec<_>) = map.into_iter().unzip();
@@ -57,6 +72,7 @@ pub struct PerfectMap<K, V> {
     keys: Vec<K>,
 }
 
+#[cfg(feature = "std")]
 impl<KEY: Hash + Sync, VALUE: Hash + Sync> PerfectMap<KEY, VALUE> {
     pub fn from_map_invert<U: Into<VALUE>>(map: HashMap<U, KEY>) -> PerfectMap<KEY, VALUE> {
         let (values, keys): (Vec<_>, Vec<_>) = map.into_iter().unzip();
@@ -65,25 +81,28 @@ impl<KEY: Hash + Sync, VALUE: Hash + Sync> PerfectMap<KEY, VALUE> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<K: Hash + Sync, V> PerfectMap<K, V> {
     pub fn from_map<U: Into<V>>(map: HashMap<K, U>) -> PerfectMap<K, V> {
         let (keys, values): (Vec<_>, Vec<_>) = map.into_iter().unzip();
 
         PerfectMap::new(&keys, values)
     }
+}
 
+impl<K: Hash + Sync, V> PerfectMap<K, V> {
     pub fn new<U: Into<V>>(keys: &[K], values: Vec<U>) -> PerfectMap<K, V> {
         assert!(keys.len() == values.len());
 
         let hasher = GOFunction::from_slice_with_conf(
-            &keys,
+            keys,
             GOBuildConf::with_lsize(GOConf::default(), 300),
         );
 
         let map_len = values.len();
         let mut reordered_vals = Vec::with_capacity(map_len);
 
-        for (k, v) in keys.into_iter().zip(values.into_iter()) {
+        for (k, v) in keys.iter().zip(values) {
             let new_idx = hasher.get(&k).unwrap() as usize;
             reordered_vals.spare_capacity_mut()[new_idx].write(v.into());
         }
@@ -111,7 +130,7 @@ impl<K: Hash + Sync, V> PerfectMap<K, V> {
         let mut reordered_vals = Vec::with_capacity(map_len);
         let mut reordered_keys = Vec::with_capacity(map_len);
 
-        for (k, v) in keys.into_iter().zip(values.into_iter()) {
+        for (k, v) in keys.into_iter().zip(values) {
             let new_idx = hasher.get(&k).unwrap() as usize;
             reordered_vals.spare_capacity_mut()[new_idx].write(v.into());
             reordered_keys.spare_capacity_mut()[new_idx].write(k);
@@ -139,6 +158,119 @@ impl<K: Hash + Sync, V> PerfectMap<K, V> {
     pub fn values(&self) -> impl Iterator<Item = &V> {
         self.values.iter()
     }
+
+    /// Whether this map retained its keys (via [`PerfectMap::new_preserve_keys`]).
+    ///
+    /// Maps built with [`PerfectMap::new`] or deserialized from data that never
+    /// carried a `keys` field report `false` here.
+    pub fn has_keys(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Iterates over `(key, value)` pairs, yielding nothing if the keys weren't
+    /// preserved. See [`PerfectMap::has_keys`].
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys.iter().zip(self.values.iter())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V: bytemuck::Pod> PerfectMap<K, V> {
+    const MAGIC: [u8; 4] = *b"PMv1";
+    const VERSION: u8 = 1;
+
+    /// Writes this map as `[magic][version][value count][values][function length][function]`,
+    /// streaming the hash function straight from [`GOFunction::write`] instead of
+    /// buffering it through a `serde::Serializer` first. Keys aren't written; a
+    /// map read back with [`PerfectMap::read_from`] always has `has_keys() == false`.
+    ///
+    /// `V` must be [`bytemuck::Pod`] (no padding, every bit pattern valid) since
+    /// `values` is written as a raw memory dump. That dump is native-endian and
+    /// not guaranteed stable across compiler versions, so this format is meant
+    /// for persisting/mmap-ing a map on the same machine that wrote it, not as
+    /// a portable interchange format.
+    pub fn write_to<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+        w.write_all(&Self::MAGIC)?;
+        w.write_all(&[Self::VERSION])?;
+        w.write_all(&(self.values.len() as u32).to_le_bytes())?;
+        w.write_all(bytemuck::cast_slice(&self.values))?;
+
+        w.write_all(&(self.function.write_bytes() as u32).to_le_bytes())?;
+        self.function
+            .write(w)
+            .map_err(|_| std::io::Error::other("couldn't write hash function"))?;
+
+        Ok(())
+    }
+
+    /// Reads a map written by [`PerfectMap::write_to`]. See that method's docs
+    /// for the `V: Pod` layout and portability constraints.
+    pub fn read_from<R: std::io::Read>(r: &mut R) -> std::io::Result<Self> {
+        use std::io::{Error, ErrorKind, Read};
+
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "bad magic for PerfectMap"));
+        }
+
+        let mut version = [0u8; 1];
+        r.read_exact(&mut version)?;
+        if version[0] != Self::VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "unsupported PerfectMap version",
+            ));
+        }
+
+        let mut len_bytes = [0u8; 4];
+        r.read_exact(&mut len_bytes)?;
+        let len = u32::from_le_bytes(len_bytes) as usize;
+
+        let byte_len = len
+            .checked_mul(std::mem::size_of::<V>())
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "value count overflows usize"))?;
+
+        // Fallibly reserve rather than `Vec::with_capacity`, so a corrupt or
+        // hostile length prefix (up to `u32::MAX * size_of::<V>()`) returns an
+        // error instead of aborting the process.
+        let mut value_bytes: Vec<u8> = Vec::new();
+        value_bytes
+            .try_reserve_exact(byte_len)
+            .map_err(|_| Error::new(ErrorKind::OutOfMemory, "value count too large to allocate"))?;
+        value_bytes.resize(byte_len, 0u8);
+        r.read_exact(&mut value_bytes)?;
+        // `pod_collect_to_vec` copies element-wise instead of reinterpreting
+        // `value_bytes` in place, so it doesn't require the `Vec<u8>` (alignment 1)
+        // to already satisfy `V`'s alignment the way `cast_slice` would.
+        let values: Vec<V> = bytemuck::pod_collect_to_vec(&value_bytes);
+
+        let mut function_len_bytes = [0u8; 4];
+        r.read_exact(&mut function_len_bytes)?;
+        let function_len = u32::from_le_bytes(function_len_bytes) as u64;
+
+        // Bound the function read to its declared length so a truncated or
+        // over-long `function_len` is actually caught instead of ignored.
+        let mut limited = r.take(function_len);
+        let function = GOFunction::read(&mut limited).map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidData,
+                "invalid bytes: expected bytes representing a ph::GOFunction",
+            )
+        })?;
+        if limited.limit() != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "function shorter than its declared length",
+            ));
+        }
+
+        Ok(PerfectMap {
+            function,
+            values,
+            keys: Vec::new(),
+        })
+    }
 }
 
 impl<K, Q: ?Sized, V> Index<&Q> for PerfectMap<K, V>
@@ -165,15 +297,13 @@ impl<K: serde::Serialize, V: serde::Serialize> serde::Serialize for PerfectMap<K
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer {
-        use serde::ser::{SerializeStruct, Error};
+        use serde::ser::SerializeStruct;
+        use crate::function_bytes::FunctionBytesRef;
 
         let mut state = serializer.serialize_struct("PerfectMap", 3)?;
         state.serialize_field("values", &self.values)?;
         state.serialize_field("keys", &self.keys)?;
-
-        let mut hasher_bytes = Vec::with_capacity(self.function.write_bytes());
-        self.function.write(&mut hasher_bytes).map_err(|_| S::Error::custom("couldn't write hash function"))?; 
-        state.serialize_field("function", &hasher_bytes)?;
+        state.serialize_field("function", &FunctionBytesRef(&self.function))?;
         state.end()
     }
 }
@@ -184,7 +314,8 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: serde::Deserializer<'de> {
-        
+        use crate::function_bytes::FunctionBytes;
+
         #[derive(serde::Deserialize)]
         #[serde(field_identifier, rename_all = "lowercase")]
         enum Field { Keys, Values, Function }
@@ -198,7 +329,7 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
         impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::de::Visitor<'de> for PerfectMapVisitor<K, V> {
             type Value = PerfectMap<K, V>;
 
-            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
                 formatter.write_str("struct PerfectMap")
             }
 
@@ -206,12 +337,15 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                 where
                     A: serde::de::SeqAccess<'de>, {
                 let values: Vec<V> = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(0, &self))?;
+                // Unlike `visit_map`, a seq has no field names to tell "keys omitted"
+                // apart from "function shifted into the keys slot" - a missing middle
+                // element would silently decode the function bytes as `Vec<K>` instead.
+                // So the forward-compat empty-keys default below is map-only; sequence
+                // formats must still carry all three positions.
                 let keys: Vec<K> = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(1, &self))?;
-                let function_bytes: Vec<u8> = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
-                
-                let function = GOFunction::read(&mut function_bytes.as_slice()).map_err(|_| serde::de::Error::custom("invalid bytes: expected bytes representing a ph::GOFunction"))?;
+                let function: FunctionBytes = seq.next_element()?.ok_or_else(|| serde::de::Error::invalid_length(2, &self))?;
 
-                Ok(PerfectMap { function, values, keys })
+                Ok(PerfectMap { function: function.0, values, keys })
             }
             
             fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
@@ -219,14 +353,14 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                     A: serde::de::MapAccess<'de>, {
                 let mut values: Option<Vec<V>> = None;
                 let mut keys: Option<Vec<K>> = None;
-                let mut function_bytes: Option<Vec<u8>> = None;
+                let mut function: Option<FunctionBytes> = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
                         Field::Function => {
-                            if function_bytes.is_some() { return Err(serde::de::Error::duplicate_field("function")) };
+                            if function.is_some() { return Err(serde::de::Error::duplicate_field("function")) };
 
-                            function_bytes = Some(map.next_value()?);
+                            function = Some(map.next_value()?);
                         },
                         Field::Values => {
                             if values.is_some() { return Err(serde::de::Error::duplicate_field("values")) };
@@ -234,23 +368,28 @@ impl<'de, K: serde::Deserialize<'de>, V: serde::Deserialize<'de>> serde::Deseria
                         },
                         Field::Keys => {
                             if keys.is_some() { return Err(serde::de::Error::duplicate_field("keys")) };
-                            keys = Some(map.next_value()?);
+                            // `KeylessPerfectMap` serializes its `PhantomData<K>` keys
+                            // field as a unit/null rather than omitting it, so accept
+                            // `Option<Vec<K>>` here instead of `Vec<K>` directly -
+                            // otherwise a `null` value fails instead of meaning "empty".
+                            let k: Option<Vec<K>> = map.next_value()?;
+                            keys = Some(k.unwrap_or_default());
                         },
-                        
+
                     }
                 }
-                
-                let function_bytes: Vec<u8> = function_bytes.ok_or_else(|| serde::de::Error::missing_field("function"))?;
-                let values = values.ok_or_else(|| serde::de::Error::missing_field("values"))?;
-                let keys = keys.ok_or_else(|| serde::de::Error::missing_field("keys"))?;
-                let function = GOFunction::read(&mut function_bytes.as_slice()).map_err(|_| serde::de::Error::custom("invalid bytes: expected bytes representing a ph::GOFunction"))?;
 
+                let function: FunctionBytes = function.ok_or_else(|| serde::de::Error::missing_field("function"))?;
+                let values = values.ok_or_else(|| serde::de::Error::missing_field("values"))?;
+                // Absent, like an empty array or `null`, means the keys weren't
+                // preserved (e.g. this was a `KeylessPerfectMap`, or `PerfectMap::new`).
+                let keys = keys.unwrap_or_default();
 
-                Ok(PerfectMap { function, values, keys })
+                Ok(PerfectMap { function: function.0, values, keys })
             }
         }
         
-        const FIELDS: &'static [&'static str] = &["values", "keys", "function"];
+        const FIELDS: &[&str] = &["values", "keys", "function"];
         deserializer.deserialize_struct("PerfectMap", FIELDS, PerfectMapVisitor { spooky: PhantomData })
     }
 }
@@ -277,4 +416,115 @@ mod test {
         assert_eq!(deserialized_map.get("c"), Some(&3i32));
         assert_eq!(deserialized_map.get("d"), Some(&4i32));
     }
+
+    // `test_serde` above only exercises `serde_json`, a human-readable format that
+    // takes the base64 `visit_str` path for the hash function. `bincode` is not
+    // human-readable, so this covers `serialize_bytes`/`visit_borrowed_bytes` instead -
+    // the byte-string path that's the whole point of encoding the function as
+    // bytes rather than a per-byte sequence.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_binary() {
+        use crate::PerfectMap;
+
+        let map: PerfectMap<String, i32> =
+            PerfectMap::new(&["a".into(), "b".into(), "c".into(), "d".into()], vec![1, 2, 3, 4]);
+
+        let serialized_map = bincode::serialize(&map).unwrap();
+        let deserialized_map: PerfectMap<String, i32> = bincode::deserialize(&serialized_map).unwrap();
+
+        assert_eq!(deserialized_map.get("a"), Some(&1i32));
+        assert_eq!(deserialized_map.get("b"), Some(&2i32));
+        assert_eq!(deserialized_map.get("c"), Some(&3i32));
+        assert_eq!(deserialized_map.get("d"), Some(&4i32));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_keyless_interop() {
+        use crate::{KeylessPerfectMap, PerfectMap};
+
+        let keyless: KeylessPerfectMap<String, i32> =
+            KeylessPerfectMap::new(vec!["a".into(), "b".into(), "c".into()], vec![1, 2, 3]);
+
+        // A `KeylessPerfectMap` serializes its `keys` field as `null` rather than
+        // omitting it; `PerfectMap`'s deserializer must still accept that as
+        // "keys weren't preserved" instead of failing to decode `null` as `Vec<K>`.
+        let serialized = serde_json::to_string(&keyless).unwrap();
+        let map: PerfectMap<String, i32> = serde_json::from_str(&serialized).unwrap();
+
+        assert!(!map.has_keys());
+        assert_eq!(map.get("a"), Some(&1i32));
+        assert_eq!(map.get("b"), Some(&2i32));
+        assert_eq!(map.get("c"), Some(&3i32));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_write_read_roundtrip() {
+        use crate::PerfectMap;
+
+        let map: PerfectMap<String, i32> =
+            PerfectMap::new(&["a".into(), "b".into(), "c".into(), "d".into()], vec![1, 2, 3, 4]);
+
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+
+        let read_back: PerfectMap<String, i32> = PerfectMap::read_from(&mut bytes.as_slice()).unwrap();
+
+        assert!(!read_back.has_keys());
+        assert_eq!(read_back.get("a"), Some(&1i32));
+        assert_eq!(read_back.get("b"), Some(&2i32));
+        assert_eq!(read_back.get("c"), Some(&3i32));
+        assert_eq!(read_back.get("d"), Some(&4i32));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from_rejects_bad_magic() {
+        use crate::PerfectMap;
+
+        let map: PerfectMap<String, i32> = PerfectMap::new(&["a".into()], vec![1]);
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+        bytes[0] = b'X';
+
+        match PerfectMap::<String, i32>::read_from(&mut bytes.as_slice()) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidData),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from_rejects_bad_version() {
+        use crate::PerfectMap;
+
+        let map: PerfectMap<String, i32> = PerfectMap::new(&["a".into()], vec![1]);
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+        bytes[4] = 0xFF;
+
+        match PerfectMap::<String, i32>::read_from(&mut bytes.as_slice()) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidData),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from_rejects_truncated_function() {
+        use crate::PerfectMap;
+
+        let map: PerfectMap<String, i32> =
+            PerfectMap::new(&["a".into(), "b".into(), "c".into()], vec![1, 2, 3]);
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+        bytes.pop();
+
+        match PerfectMap::<String, i32>::read_from(&mut bytes.as_slice()) {
+            Ok(_) => panic!("expected an error"),
+            Err(err) => assert_eq!(err.kind(), std::io::ErrorKind::InvalidData),
+        }
+    }
 }
\ No newline at end of file